@@ -1,4 +1,8 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ed25519_dalek::SigningKey;
 use keyring::Entry;
+use rand::rngs::OsRng;
+use rand::RngCore;
 
 const SERVICE_NAME: &str = "pin-client";
 
@@ -22,6 +26,39 @@ pub fn delete_credentials(client_id: &str) -> Result<(), Box<dyn std::error::Err
     Ok(())
 }
 
+fn ed25519_entry(client_id: &str) -> Result<Entry, Box<dyn std::error::Error>> {
+    Ok(Entry::new(SERVICE_NAME, &format!("{}:ed25519", client_id))?)
+}
+
+/// Generates a fresh ed25519 keypair and stores the private key in the OS
+/// keychain, base64-encoded. Returns the base64-encoded public key to register
+/// with the server; the private key never leaves this function.
+pub fn generate_and_store_keypair(client_id: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let mut seed = [0u8; 32];
+    OsRng.fill_bytes(&mut seed);
+    let signing_key = SigningKey::from_bytes(&seed);
+
+    store_keypair(client_id, &signing_key)?;
+    Ok(STANDARD.encode(signing_key.verifying_key().to_bytes()))
+}
+
+pub fn store_keypair(client_id: &str, signing_key: &SigningKey) -> Result<(), Box<dyn std::error::Error>> {
+    let entry = ed25519_entry(client_id)?;
+    entry.set_password(&STANDARD.encode(signing_key.to_bytes()))?;
+    log::info!("Ed25519 keypair stored securely for client: {}", client_id);
+    Ok(())
+}
+
+pub fn get_signing_key(client_id: &str) -> Result<SigningKey, Box<dyn std::error::Error>> {
+    let entry = ed25519_entry(client_id)?;
+    let encoded = entry.get_password()?;
+    let bytes = STANDARD.decode(encoded)?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| "stored ed25519 key has an unexpected length")?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,7 +71,18 @@ mod tests {
         store_credentials(test_id, test_secret).unwrap();
         let retrieved = get_credentials(test_id).unwrap();
         assert_eq!(retrieved, test_secret);
-        
+
         delete_credentials(test_id).unwrap();
     }
+
+    #[test]
+    fn test_keypair_roundtrip() {
+        let test_id = "test_client_ed25519_456";
+
+        let public_key = generate_and_store_keypair(test_id).unwrap();
+        let signing_key = get_signing_key(test_id).unwrap();
+        assert_eq!(STANDARD.encode(signing_key.verifying_key().to_bytes()), public_key);
+
+        ed25519_entry(test_id).unwrap().delete_credential().unwrap();
+    }
 }
@@ -2,11 +2,13 @@
 
 mod websocket;
 mod keychain;
+mod metrics;
 mod ollama;
 
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use tauri::{
     Manager,
@@ -14,11 +16,90 @@ use tauri::{
     menu::{Menu, MenuItem},
 };
 
-static APP_STATE: Lazy<Arc<RwLock<AppState>>> = Lazy::new(|| {
-    Arc::new(RwLock::new(AppState::default()))
-});
+static APP_STATE: Lazy<Arc<SharedState>> = Lazy::new(|| Arc::new(SharedState::default()));
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Selects how the client proves its identity to the PIN server.
+/// `Ed25519` keeps the private key on-device; the server only ever learns
+/// the public key, so it can never impersonate the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuthMode {
+    SharedSecret,
+    Ed25519,
+}
+
+impl Default for AuthMode {
+    fn default() -> Self {
+        AuthMode::SharedSecret
+    }
+}
+
+/// The fields touched on every inference request (`connected`, `current_load`,
+/// `total_requests`) live as atomics so the per-request hot path never contends
+/// with status reads from the UI. Everything else is structured enough (or
+/// changes rarely enough) to stay behind a lock.
+#[derive(Default)]
+pub struct SharedState {
+    pub(crate) inner: RwLock<StateInner>,
+    pub(crate) connected: AtomicBool,
+    pub(crate) current_load: AtomicU32,
+    pub(crate) total_requests: AtomicU64,
+}
+
+impl SharedState {
+    fn snapshot(&self) -> AppState {
+        let inner = self.inner.read();
+        AppState {
+            client_id: inner.client_id.clone(),
+            operator_id: inner.operator_id.clone(),
+            server_url: inner.server_url.clone(),
+            ollama_url: inner.ollama_url.clone(),
+            connected: self.connected.load(Ordering::Relaxed),
+            last_heartbeat: inner.last_heartbeat.clone(),
+            models: inner.models.clone(),
+            current_load: self.current_load.load(Ordering::Relaxed),
+            total_requests: self.total_requests.load(Ordering::Relaxed),
+            capacity: inner.capacity,
+            auth_mode: inner.auth_mode,
+            reconnect_attempts: inner.reconnect_attempts,
+            allow_legacy_fallback: inner.allow_legacy_fallback,
+        }
+    }
+}
+
+pub(crate) struct StateInner {
+    pub(crate) client_id: Option<String>,
+    pub(crate) operator_id: Option<String>,
+    pub(crate) server_url: String,
+    pub(crate) ollama_url: String,
+    pub(crate) last_heartbeat: Option<String>,
+    pub(crate) models: Vec<String>,
+    pub(crate) capacity: u32,
+    pub(crate) auth_mode: AuthMode,
+    pub(crate) reconnect_attempts: u32,
+    pub(crate) allow_legacy_fallback: bool,
+}
+
+impl Default for StateInner {
+    fn default() -> Self {
+        Self {
+            client_id: None,
+            operator_id: None,
+            server_url: String::new(),
+            ollama_url: String::new(),
+            last_heartbeat: None,
+            models: Vec::new(),
+            capacity: DEFAULT_CAPACITY,
+            auth_mode: AuthMode::SharedSecret,
+            reconnect_attempts: 0,
+            allow_legacy_fallback: false,
+        }
+    }
+}
+
+const DEFAULT_CAPACITY: u32 = 4;
+
+/// Serializable snapshot of [`SharedState`] handed to the frontend by `get_status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppState {
     pub client_id: Option<String>,
     pub operator_id: Option<String>,
@@ -29,6 +110,10 @@ pub struct AppState {
     pub models: Vec<String>,
     pub current_load: u32,
     pub total_requests: u64,
+    pub capacity: u32,
+    pub auth_mode: AuthMode,
+    pub reconnect_attempts: u32,
+    pub allow_legacy_fallback: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,32 +122,64 @@ pub struct ConnectionConfig {
     pub api_secret: String,
     pub server_url: String,
     pub ollama_url: String,
+    #[serde(default)]
+    pub auth_mode: AuthMode,
+    #[serde(default = "default_capacity")]
+    pub capacity: u32,
+    /// Opts into falling back to the legacy (pre-HELLO, replayable-signature) AUTH
+    /// flow for servers that don't understand HELLO. Off by default: a server that
+    /// doesn't support the HELLO handshake should be upgraded, not silently
+    /// tolerated, since the legacy flow is weaker.
+    #[serde(default)]
+    pub allow_legacy_fallback: bool,
+}
+
+fn default_capacity() -> u32 {
+    DEFAULT_CAPACITY
 }
 
 #[tauri::command]
 async fn get_status() -> Result<AppState, String> {
-    Ok(APP_STATE.read().clone())
+    Ok(APP_STATE.snapshot())
 }
 
 #[tauri::command]
 async fn save_credentials(config: ConnectionConfig) -> Result<String, String> {
     keychain::store_credentials(&config.client_id, &config.api_secret)
         .map_err(|e| format!("Failed to store credentials: {}", e))?;
-    
-    let mut state = APP_STATE.write();
-    state.client_id = Some(config.client_id);
-    state.server_url = config.server_url;
-    state.ollama_url = config.ollama_url;
-    
+
+    let mut inner = APP_STATE.inner.write();
+    inner.client_id = Some(config.client_id);
+    inner.server_url = config.server_url;
+    inner.ollama_url = config.ollama_url;
+    inner.auth_mode = config.auth_mode;
+    inner.capacity = config.capacity.max(1);
+    inner.allow_legacy_fallback = config.allow_legacy_fallback;
+
     Ok("Credentials saved securely".to_string())
 }
 
+/// Enrolls the device for ed25519 auth: generates a keypair, stores the
+/// private key in the OS keychain, and returns the base64 public key for the
+/// operator to register with the server. The private key never leaves this call.
+#[tauri::command]
+async fn enroll_ed25519(client_id: String) -> Result<String, String> {
+    let public_key = keychain::generate_and_store_keypair(&client_id)
+        .map_err(|e| format!("Failed to generate device keypair: {}", e))?;
+
+    let mut inner = APP_STATE.inner.write();
+    inner.client_id = Some(client_id);
+    inner.auth_mode = AuthMode::Ed25519;
+
+    Ok(public_key)
+}
+
 #[tauri::command]
 async fn load_credentials() -> Result<Option<(String, String)>, String> {
-    let state = APP_STATE.read();
-    if let Some(client_id) = &state.client_id {
-        match keychain::get_credentials(client_id) {
-            Ok(secret) => Ok(Some((client_id.clone(), secret))),
+    let client_id = APP_STATE.inner.read().client_id.clone();
+    if let Some(client_id) = client_id {
+        match keychain::get_credentials(&client_id) {
+            Ok(secret) => Ok(Some((client_id, secret))),
             Err(_) => Ok(None),
         }
     } else {
@@ -71,20 +188,33 @@ async fn load_credentials() -> Result<Option<(String, String)>, String> {
 }
 
 #[tauri::command]
-async fn connect(_app: tauri::AppHandle) -> Result<String, String> {
-    let (client_id, server_url, ollama_url) = {
-        let state = APP_STATE.read();
+async fn connect(app: tauri::AppHandle) -> Result<String, String> {
+    let (client_id, server_url, ollama_url, capacity, auth_mode, allow_legacy_fallback) = {
+        let inner = APP_STATE.inner.read();
         (
-            state.client_id.clone(),
-            state.server_url.clone(),
-            state.ollama_url.clone(),
+            inner.client_id.clone(),
+            inner.server_url.clone(),
+            inner.ollama_url.clone(),
+            inner.capacity,
+            inner.auth_mode,
+            inner.allow_legacy_fallback,
         )
     };
-    
+
     let client_id = client_id.ok_or("No client ID configured")?;
-    let api_secret = keychain::get_credentials(&client_id)
-        .map_err(|e| format!("Failed to get credentials: {}", e))?;
-    
+    let credential = match auth_mode {
+        AuthMode::SharedSecret => {
+            let api_secret = keychain::get_credentials(&client_id)
+                .map_err(|e| format!("Failed to get credentials: {}", e))?;
+            websocket::AuthCredential::SharedSecret { api_secret, allow_legacy_fallback }
+        }
+        AuthMode::Ed25519 => {
+            let signing_key = keychain::get_signing_key(&client_id)
+                .map_err(|e| format!("Failed to get device signing key: {}", e))?;
+            websocket::AuthCredential::Ed25519(signing_key)
+        }
+    };
+
     let server_url = if server_url.is_empty() {
         "wss://aiassist-secure.replit.app/api/v1/pin/ws".to_string()
     } else {
@@ -99,21 +229,19 @@ async fn connect(_app: tauri::AppHandle) -> Result<String, String> {
     };
     
     tauri::async_runtime::spawn(async move {
-        if let Err(e) = websocket::connect_to_server(&server_url, &client_id, &api_secret, &ollama_url).await {
+        if let Err(e) = websocket::connect_to_server(&server_url, &client_id, credential, &ollama_url, capacity, app).await {
             log::error!("WebSocket connection error: {}", e);
-            let mut state = APP_STATE.write();
-            state.connected = false;
+            APP_STATE.connected.store(false, Ordering::Relaxed);
         }
     });
-    
+
     Ok("Connecting...".to_string())
 }
 
 #[tauri::command]
 async fn disconnect() -> Result<String, String> {
     websocket::disconnect();
-    let mut state = APP_STATE.write();
-    state.connected = false;
+    APP_STATE.connected.store(false, Ordering::Relaxed);
     Ok("Disconnected".to_string())
 }
 
@@ -122,14 +250,19 @@ async fn test_ollama(url: String) -> Result<Vec<String>, String> {
     ollama::test_connection(&url).await
 }
 
+#[tauri::command]
+async fn get_metrics_port() -> Result<u16, String> {
+    Ok(metrics::port())
+}
+
 #[tauri::command]
 async fn get_ollama_models() -> Result<Vec<String>, String> {
     let url = {
-        let state = APP_STATE.read();
-        if state.ollama_url.is_empty() {
+        let inner = APP_STATE.inner.read();
+        if inner.ollama_url.is_empty() {
             "http://localhost:11434".to_string()
         } else {
-            state.ollama_url.clone()
+            inner.ollama_url.clone()
         }
     };
     ollama::get_models(&url).await
@@ -142,6 +275,12 @@ fn main() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_notification::init())
         .setup(|app| {
+            tauri::async_runtime::spawn(async {
+                if let Err(e) = metrics::start().await {
+                    log::error!("Failed to start metrics endpoint: {}", e);
+                }
+            });
+
             let show_item = MenuItem::with_id(app, "show", "Show Window", true, None::<&str>)?;
             let connect_item = MenuItem::with_id(app, "connect", "Connect", true, None::<&str>)?;
             let disconnect_item = MenuItem::with_id(app, "disconnect", "Disconnect", true, None::<&str>)?;
@@ -195,10 +334,12 @@ fn main() {
             get_status,
             save_credentials,
             load_credentials,
+            enroll_ed25519,
             connect,
             disconnect,
             test_ollama,
             get_ollama_models,
+            get_metrics_port,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,115 @@
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+use std::sync::atomic::{AtomicU16, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+pub static TOTAL_INFERENCE_REQUESTS: Lazy<IntCounter> = Lazy::new(|| {
+    let counter = IntCounter::new(
+        "pin_client_inference_requests_total",
+        "Total inference requests handled",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+pub static CURRENT_LOAD: Lazy<IntGauge> = Lazy::new(|| {
+    let gauge = IntGauge::new("pin_client_current_load", "In-flight inference requests").unwrap();
+    REGISTRY.register(Box::new(gauge.clone())).unwrap();
+    gauge
+});
+
+pub static REQUESTS_BY_MODEL: Lazy<IntCounterVec> = Lazy::new(|| {
+    let counter_vec = IntCounterVec::new(
+        Opts::new(
+            "pin_client_inference_requests_by_model_total",
+            "Inference requests handled, by model",
+        ),
+        &["model"],
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter_vec.clone())).unwrap();
+    counter_vec
+});
+
+pub static INFERENCE_LATENCY: Lazy<Histogram> = Lazy::new(|| {
+    let histogram = Histogram::with_opts(HistogramOpts::new(
+        "pin_client_inference_latency_seconds",
+        "Inference request latency in seconds",
+    ))
+    .unwrap();
+    REGISTRY.register(Box::new(histogram.clone())).unwrap();
+    histogram
+});
+
+pub static RECONNECT_COUNT: Lazy<IntCounter> = Lazy::new(|| {
+    let counter = IntCounter::new(
+        "pin_client_reconnects_total",
+        "Total websocket reconnect attempts",
+    )
+    .unwrap();
+    REGISTRY.register(Box::new(counter.clone())).unwrap();
+    counter
+});
+
+static METRICS_PORT: AtomicU16 = AtomicU16::new(0);
+
+/// Port the local metrics endpoint is listening on, or 0 if `start` hasn't run yet.
+pub fn port() -> u16 {
+    METRICS_PORT.load(Ordering::Relaxed)
+}
+
+/// Starts the local Prometheus metrics endpoint on an OS-assigned loopback
+/// port and returns it. Any request to the socket gets the current metrics
+/// snapshot back; there's no routing since it only ever serves one thing.
+pub async fn start() -> std::io::Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let port = listener.local_addr()?.port();
+    METRICS_PORT.store(port, Ordering::Relaxed);
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((socket, _)) => {
+                    tokio::spawn(serve(socket));
+                }
+                Err(e) => {
+                    log::error!("Metrics listener error: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+
+    log::info!("Metrics endpoint listening on 127.0.0.1:{}", port);
+    Ok(port)
+}
+
+async fn serve(mut socket: TcpStream) {
+    let mut buf = [0u8; 1024];
+    // The request itself is irrelevant: every connection gets the full snapshot.
+    let _ = socket.read(&mut buf).await;
+
+    let encoder = TextEncoder::new();
+    let metric_families = REGISTRY.gather();
+    let mut buffer = Vec::new();
+    if encoder.encode(&metric_families, &mut buffer).is_err() {
+        return;
+    }
+
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        encoder.format_type(),
+        buffer.len()
+    );
+
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.write_all(&buffer).await;
+    let _ = socket.shutdown().await;
+}
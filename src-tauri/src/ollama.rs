@@ -1,5 +1,7 @@
+use futures_util::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OllamaModel {
@@ -110,6 +112,147 @@ pub async fn chat_completion(
     Ok(result)
 }
 
+/// Streams a chat completion from Ollama, pushing each NDJSON chunk to `tx` as it
+/// arrives instead of buffering the whole reply. The final chunk (`done: true`)
+/// carries the aggregated token counts and ends the stream.
+pub async fn chat_completion_stream(
+    url: String,
+    model: String,
+    messages: Vec<ChatMessage>,
+    tx: mpsc::Sender<Result<OllamaChatResponse, String>>,
+) -> Result<(), String> {
+    let client = Client::new();
+    let api_url = format!("{}/api/chat", url.trim_end_matches('/'));
+
+    let request = OllamaChatRequest {
+        model,
+        messages,
+        stream: Some(true),
+        options: None,
+    };
+
+    let response = client
+        .post(&api_url)
+        .json(&request)
+        .timeout(std::time::Duration::from_secs(120))
+        .send()
+        .await
+        .map_err(|e| format!("Ollama request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let _ = tx.send(Err(format!("Ollama error {}: {}", status, body))).await;
+        return Ok(());
+    }
+
+    let mut body = response.bytes_stream();
+    let mut buf: Vec<u8> = Vec::new();
+
+    while let Some(next) = body.next().await {
+        let bytes = match next {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                let _ = tx.send(Err(format!("Ollama stream error: {}", e))).await;
+                return Ok(());
+            }
+        };
+        buf.extend_from_slice(&bytes);
+
+        while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buf.drain(..=pos).collect();
+            let line = line.strip_suffix(b"\n").unwrap_or(&line);
+            if line.is_empty() {
+                continue;
+            }
+
+            let chunk: OllamaChatResponse = match serde_json::from_slice(line) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    let _ = tx.send(Err(format!("Failed to parse Ollama chunk: {}", e))).await;
+                    return Ok(());
+                }
+            };
+
+            let done = chunk.done;
+            if tx.send(Ok(chunk)).await.is_err() {
+                // Receiver dropped, nothing left to stream to.
+                return Ok(());
+            }
+            if done {
+                return Ok(());
+            }
+        }
+    }
+
+    // The connection closed before a `done: true` chunk arrived, so the
+    // generation was cut short rather than finished cleanly.
+    let _ = tx
+        .send(Err("Ollama stream ended before completion".to_string()))
+        .await;
+    Ok(())
+}
+
 pub fn estimate_tokens(text: &str) -> u32 {
     (text.len() as f32 / 4.0).ceil() as u32
 }
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OllamaPsResponse {
+    #[serde(default)]
+    models: Vec<OllamaRunningModel>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaRunningModel {
+    #[serde(default)]
+    size_vram: u64,
+}
+
+/// Sums the VRAM Ollama reports as in-use by loaded models via `/api/ps`.
+/// Returns 0 (rather than erroring) if Ollama is unreachable or too old to
+/// support the endpoint, since this only feeds best-effort heartbeat telemetry.
+pub async fn get_gpu_memory_used(url: &str) -> u64 {
+    let client = Client::new();
+    let api_url = format!("{}/api/ps", url.trim_end_matches('/'));
+
+    let response = match client
+        .get(&api_url)
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+    {
+        Ok(response) if response.status().is_success() => response,
+        _ => return 0,
+    };
+
+    match response.json::<OllamaPsResponse>().await {
+        Ok(data) => data.models.iter().map(|m| m.size_vram).sum(),
+        Err(_) => 0,
+    }
+}
+
+/// Falls back to `nvidia-smi` for total GPU memory, since Ollama's API doesn't
+/// expose it. Returns 0 if the binary isn't present (e.g. no NVIDIA GPU).
+pub async fn get_gpu_memory_total() -> u64 {
+    tokio::task::spawn_blocking(nvidia_smi_memory_total)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or(0)
+}
+
+fn nvidia_smi_memory_total() -> Option<u64> {
+    let output = std::process::Command::new("nvidia-smi")
+        .args(["--query-gpu=memory.total", "--format=csv,noheader,nounits"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let megabytes: u64 = text.lines().next()?.trim().parse().ok()?;
+    Some(megabytes * 1024 * 1024)
+}
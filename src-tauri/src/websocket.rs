@@ -1,15 +1,42 @@
-use crate::{ollama, APP_STATE};
-use futures_util::{SinkExt, StreamExt};
+use crate::{metrics, ollama, APP_STATE};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ed25519_dalek::{Signer, SigningKey};
+use futures_util::{Sink, SinkExt, StreamExt};
+use hmac::{Hmac, Mac};
 use once_cell::sync::Lazy;
 use parking_lot::RwLock;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::mpsc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::{mpsc, Semaphore};
+use tokio::time::Duration;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
-static DISCONNECT_TX: Lazy<Arc<RwLock<Option<mpsc::Sender<()>>>>> = 
+type HmacSha256 = Hmac<Sha256>;
+
+/// Bumped whenever the handshake changes shape. Sent in `HELLO` so the server
+/// can negotiate; a server that doesn't understand `HELLO` at all replies with
+/// an `ERROR`, which triggers a fallback to the legacy signature-based `AUTH`
+/// only when the shared-secret credential has `allow_legacy_fallback` set.
+/// Otherwise (and always for ed25519) the handshake fails.
+const PROTOCOL_VERSION: u8 = 2;
+
+/// How often `connect_to_server` reports load/GPU health to the operator.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Reconnect backoff: starts at `INITIAL_RECONNECT_DELAY`, doubles on every
+/// consecutive failed attempt, and is capped at `MAX_RECONNECT_DELAY`. A random
+/// delay up to `RECONNECT_JITTER_MS` is added on top so a server restart doesn't
+/// get hit by every client reconnecting in lockstep.
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+const RECONNECT_JITTER_MS: u64 = 500;
+
+static DISCONNECT_TX: Lazy<Arc<RwLock<Option<mpsc::Sender<()>>>>> =
     Lazy::new(|| Arc::new(RwLock::new(None)));
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,6 +44,7 @@ static DISCONNECT_TX: Lazy<Arc<RwLock<Option<mpsc::Sender<()>>>>> =
 #[allow(non_camel_case_types)]
 pub enum ServerMessage {
     AUTH_SUCCESS { operator_id: String, message: String },
+    CHALLENGE { nonce: String },
     ERROR { message: String },
     PING,
     HEARTBEAT_ACK,
@@ -33,7 +61,18 @@ pub struct InferencePayload {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct AuthMessage {
+struct HelloMessage {
+    #[serde(rename = "type")]
+    msg_type: String,
+    client_id: String,
+    protocol_version: u8,
+}
+
+/// Legacy AUTH frame: `signature = SHA256(client_id || timestamp || SHA256(secret))`.
+/// Kept only as a fallback for servers that answer `HELLO` with an `ERROR` or
+/// any other non-`CHALLENGE` reply.
+#[derive(Debug, Serialize, Deserialize)]
+struct LegacyAuthMessage {
     #[serde(rename = "type")]
     msg_type: String,
     client_id: String,
@@ -41,6 +80,36 @@ struct AuthMessage {
     signature: String,
 }
 
+/// Challenge-response AUTH frame: `response = HMAC-SHA256(api_secret, client_id || ":" || nonce)`.
+#[derive(Debug, Serialize, Deserialize)]
+struct ChallengeAuthMessage {
+    #[serde(rename = "type")]
+    msg_type: String,
+    client_id: String,
+    response: String,
+}
+
+/// Challenge-response AUTH frame for ed25519 device auth: `signature = sign(private_key, nonce)`,
+/// base64-encoded.
+#[derive(Debug, Serialize, Deserialize)]
+struct Ed25519AuthMessage {
+    #[serde(rename = "type")]
+    msg_type: String,
+    client_id: String,
+    signature: String,
+}
+
+/// How `connect_to_server` proves the client's identity once the server issues a
+/// `CHALLENGE`. The shared-secret variant's `bool` is `allow_legacy_fallback`: it
+/// must be explicitly opted into (operator-configured, for servers still on the
+/// pre-`HELLO` protocol) before a HELLO `ERROR`/unexpected reply is allowed to
+/// downgrade the connection to the replayable legacy signature. Without it, a
+/// forged `ERROR` frame can't be used to force a downgrade.
+pub enum AuthCredential {
+    SharedSecret { api_secret: String, allow_legacy_fallback: bool },
+    Ed25519(SigningKey),
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ClientMessage {
     #[serde(rename = "type")]
@@ -55,6 +124,10 @@ struct ClientMessage {
     models: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     health: Option<HealthReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delta: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -69,50 +142,375 @@ fn compute_signature(client_id: &str, timestamp: &str, api_secret: &str) -> Stri
     let mut hasher = Sha256::new();
     hasher.update(api_secret.as_bytes());
     let secret_hash = hex::encode(hasher.finalize());
-    
+
     let mut sig_hasher = Sha256::new();
     sig_hasher.update(format!("{}{}{}", client_id, timestamp, secret_hash).as_bytes());
     hex::encode(sig_hasher.finalize())
 }
 
-pub async fn connect_to_server(
-    server_url: &str,
+/// Computes the challenge-response MAC for a server-issued nonce. The nonce is
+/// single-use and server-chosen, so a captured `AUTH` frame can't be replayed
+/// the way the legacy timestamp-windowed signature could.
+fn compute_hmac(client_id: &str, nonce: &str, api_secret: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(api_secret.as_bytes())
+        .expect("HMAC accepts a key of any size");
+    mac.update(client_id.as_bytes());
+    mac.update(b":");
+    mac.update(nonce.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Keeps `current_load`/`metrics::CURRENT_LOAD` and inference latency accurate
+/// even when the task carrying it is aborted, e.g. by `run_session` tearing
+/// down in-flight inference on a dropped connection. The decrement/observe
+/// happens in `Drop`, which `tokio::task::JoinSet::abort_all` still runs when
+/// it drops the task's local state, unlike code at the tail of
+/// `process_inference_request` that abort skips straight past.
+struct LoadGuard {
+    started_at: Instant,
+}
+
+impl LoadGuard {
+    fn acquire() -> Self {
+        APP_STATE.current_load.fetch_add(1, Ordering::Relaxed);
+        metrics::CURRENT_LOAD.inc();
+        Self { started_at: Instant::now() }
+    }
+}
+
+impl Drop for LoadGuard {
+    fn drop(&mut self) {
+        APP_STATE.current_load.fetch_sub(1, Ordering::Relaxed);
+        metrics::CURRENT_LOAD.dec();
+        metrics::INFERENCE_LATENCY.observe(self.started_at.elapsed().as_secs_f64());
+    }
+}
+
+/// Runs the inference request to completion (streaming or not) and writes every
+/// resulting `ClientMessage` back through `outbound_tx`. Spawned onto its own task
+/// per request so a slow model never blocks the read/write halves of the socket.
+async fn process_inference_request(
+    request_id: String,
+    payload: InferencePayload,
+    ollama_url: String,
+    outbound_tx: mpsc::Sender<Message>,
+) {
+    metrics::TOTAL_INFERENCE_REQUESTS.inc();
+    metrics::REQUESTS_BY_MODEL
+        .with_label_values(&[&payload.model])
+        .inc();
+
+    if payload.stream {
+        let (chunk_tx, mut chunk_rx) = mpsc::channel(16);
+        let stream_task = tokio::spawn(ollama::chat_completion_stream(
+            ollama_url,
+            payload.model,
+            payload.messages,
+            chunk_tx,
+        ));
+
+        let mut prompt_eval_count = 0u32;
+        let mut eval_count = 0u32;
+        let mut stream_error: Option<String> = None;
+
+        while let Some(chunk) = chunk_rx.recv().await {
+            match chunk {
+                Ok(resp) => {
+                    if !resp.message.content.is_empty() {
+                        let chunk_msg = ClientMessage {
+                            msg_type: "INFERENCE_CHUNK".to_string(),
+                            request_id: Some(request_id.clone()),
+                            result: None,
+                            error: None,
+                            models: None,
+                            health: None,
+                            delta: Some(resp.message.content),
+                            kind: None,
+                        };
+                        if let Ok(text) = serde_json::to_string(&chunk_msg) {
+                            let _ = outbound_tx.send(Message::Text(text)).await;
+                        }
+                    }
+                    if resp.done {
+                        prompt_eval_count = resp.prompt_eval_count.unwrap_or(0);
+                        eval_count = resp.eval_count.unwrap_or(0);
+                    }
+                }
+                Err(e) => {
+                    stream_error = Some(e);
+                    break;
+                }
+            }
+        }
+        let _ = stream_task.await;
+
+        let done_msg = match stream_error {
+            Some(e) => ClientMessage {
+                msg_type: "INFERENCE_ERROR".to_string(),
+                request_id: Some(request_id),
+                result: None,
+                error: Some(e),
+                models: None,
+                health: None,
+                delta: None,
+                kind: None,
+            },
+            None => ClientMessage {
+                msg_type: "INFERENCE_DONE".to_string(),
+                request_id: Some(request_id),
+                result: Some(serde_json::json!({
+                    "prompt_eval_count": prompt_eval_count,
+                    "eval_count": eval_count,
+                })),
+                error: None,
+                models: None,
+                health: None,
+                delta: None,
+                kind: None,
+            },
+        };
+        if let Ok(text) = serde_json::to_string(&done_msg) {
+            let _ = outbound_tx.send(Message::Text(text)).await;
+        }
+    } else {
+        let result = ollama::chat_completion(
+            &ollama_url,
+            &payload.model,
+            payload.messages,
+            false,
+        ).await;
+
+        let response = match result {
+            Ok(resp) => ClientMessage {
+                msg_type: "INFERENCE_RESPONSE".to_string(),
+                request_id: Some(request_id),
+                result: Some(serde_json::to_value(resp).unwrap()),
+                error: None,
+                models: None,
+                health: None,
+                delta: None,
+                kind: None,
+            },
+            Err(e) => ClientMessage {
+                msg_type: "INFERENCE_ERROR".to_string(),
+                request_id: Some(request_id),
+                result: None,
+                error: Some(e),
+                models: None,
+                health: None,
+                delta: None,
+                kind: None,
+            },
+        };
+
+        if let Ok(text) = serde_json::to_string(&response) {
+            let _ = outbound_tx.send(Message::Text(text)).await;
+        }
+    }
+}
+
+/// Sends the pre-HMAC `AUTH` frame for servers that don't understand `HELLO`.
+async fn send_legacy_auth<S>(
+    write: &mut S,
     client_id: &str,
     api_secret: &str,
-    ollama_url: &str,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    log::info!("Connecting to PIN server: {}", server_url);
-    
-    let (ws_stream, _) = connect_async(server_url).await?;
-    let (mut write, mut read) = ws_stream.split();
-    
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    S: Sink<Message> + Unpin,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs()
         .to_string();
-    
+
     let signature = compute_signature(client_id, &timestamp, api_secret);
-    
-    let auth_msg = AuthMessage {
+
+    let auth_msg = LegacyAuthMessage {
         msg_type: "AUTH".to_string(),
         client_id: client_id.to_string(),
         timestamp,
         signature,
     };
-    
+
     write.send(Message::Text(serde_json::to_string(&auth_msg)?)).await?;
-    log::info!("Sent AUTH message");
-    
-    let (disconnect_tx, mut disconnect_rx) = mpsc::channel::<()>(1);
-    *DISCONNECT_TX.write() = Some(disconnect_tx);
-    
+    log::info!("Sent legacy AUTH message");
+    Ok(())
+}
+
+/// Why a session ended, so the supervisor in [`connect_to_server`] knows whether
+/// to reconnect and whether to reset its backoff delay.
+enum SessionExit {
+    UserDisconnect,
+    ConnectionLost { authenticated: bool },
+}
+
+/// Connects, authenticates, and runs a single session until it's disconnected
+/// (by the user) or drops (server close, error, stream end). Reconnection is
+/// handled by the caller, [`connect_to_server`].
+async fn run_session(
+    server_url: &str,
+    client_id: &str,
+    credential: &AuthCredential,
+    ollama_url: &str,
+    capacity: u32,
+    disconnect_rx: &mut mpsc::Receiver<()>,
+    app_handle: &tauri::AppHandle,
+) -> Result<SessionExit, Box<dyn std::error::Error + Send + Sync>> {
+    log::info!("Connecting to PIN server: {}", server_url);
+
+    let (ws_stream, _) = connect_async(server_url).await?;
+    let (mut write, mut read) = ws_stream.split();
+
+    let hello_msg = HelloMessage {
+        msg_type: "HELLO".to_string(),
+        client_id: client_id.to_string(),
+        protocol_version: PROTOCOL_VERSION,
+    };
+    write.send(Message::Text(serde_json::to_string(&hello_msg)?)).await?;
+    log::info!("Sent HELLO message");
+
+    match read.next().await {
+        Some(Ok(Message::Text(text))) => match serde_json::from_str::<ServerMessage>(&text) {
+            Ok(ServerMessage::CHALLENGE { nonce }) => match credential {
+                AuthCredential::SharedSecret { api_secret, .. } => {
+                    let response = compute_hmac(client_id, &nonce, api_secret);
+                    let auth_msg = ChallengeAuthMessage {
+                        msg_type: "AUTH".to_string(),
+                        client_id: client_id.to_string(),
+                        response,
+                    };
+                    write.send(Message::Text(serde_json::to_string(&auth_msg)?)).await?;
+                    log::info!("Sent challenge-response AUTH message");
+                }
+                AuthCredential::Ed25519(signing_key) => {
+                    let signature = signing_key.sign(nonce.as_bytes());
+                    let auth_msg = Ed25519AuthMessage {
+                        msg_type: "AUTH".to_string(),
+                        client_id: client_id.to_string(),
+                        signature: STANDARD.encode(signature.to_bytes()),
+                    };
+                    write.send(Message::Text(serde_json::to_string(&auth_msg)?)).await?;
+                    log::info!("Sent ed25519 challenge-response AUTH message");
+                }
+            },
+            Ok(ServerMessage::ERROR { message }) => match credential {
+                AuthCredential::SharedSecret { api_secret, allow_legacy_fallback: true } => {
+                    log::warn!("Server rejected HELLO ({}), falling back to legacy signature auth", message);
+                    send_legacy_auth(&mut write, client_id, api_secret).await?;
+                }
+                AuthCredential::SharedSecret { allow_legacy_fallback: false, .. } => {
+                    return Err(format!(
+                        "Server rejected HELLO ({}) and legacy fallback is not enabled for this client",
+                        message
+                    )
+                    .into());
+                }
+                AuthCredential::Ed25519(_) => {
+                    return Err(format!("Server does not support ed25519 auth: {}", message).into());
+                }
+            },
+            _ => match credential {
+                AuthCredential::SharedSecret { api_secret, allow_legacy_fallback: true } => {
+                    log::warn!("Unexpected response to HELLO, falling back to legacy signature auth");
+                    send_legacy_auth(&mut write, client_id, api_secret).await?;
+                }
+                AuthCredential::SharedSecret { allow_legacy_fallback: false, .. } => {
+                    return Err("Unexpected response to HELLO and legacy fallback is not enabled for this client".into());
+                }
+                AuthCredential::Ed25519(_) => {
+                    return Err("Unexpected response to HELLO during ed25519 handshake".into());
+                }
+            },
+        },
+        Some(Ok(Message::Close(_))) | None => {
+            return Err("Server closed connection during handshake".into());
+        }
+        Some(Ok(_)) => match credential {
+            AuthCredential::SharedSecret { api_secret, allow_legacy_fallback: true } => {
+                log::warn!("Unexpected non-text response to HELLO, falling back to legacy signature auth");
+                send_legacy_auth(&mut write, client_id, api_secret).await?;
+            }
+            AuthCredential::SharedSecret { allow_legacy_fallback: false, .. } => {
+                return Err("Unexpected non-text response to HELLO and legacy fallback is not enabled for this client".into());
+            }
+            AuthCredential::Ed25519(_) => {
+                return Err("Unexpected non-text response to HELLO during ed25519 handshake".into());
+            }
+        },
+        Some(Err(e)) => return Err(e.into()),
+    }
+
     let ollama_url = ollama_url.to_string();
-    
+
+    // The write half is owned by a dedicated task so in-flight inference tasks
+    // can reply concurrently without fighting each other (or the select loop
+    // below) for the socket.
+    let (outbound_tx, mut outbound_rx) = mpsc::channel::<Message>(64);
+    let writer_task = tokio::spawn(async move {
+        while let Some(msg) = outbound_rx.recv().await {
+            if write.send(msg).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let semaphore = Arc::new(Semaphore::new(capacity.max(1) as usize));
+
+    // Tracked so a dropped connection can abort in-flight inference rather than
+    // waiting on it: aborting drops each task's `outbound_tx` clone, which is
+    // what lets `writer_task` (and this teardown) finish promptly.
+    let mut inflight: tokio::task::JoinSet<()> = tokio::task::JoinSet::new();
+
+    // Periodically reports load and GPU memory so the operator can see this
+    // client is alive and how busy it is, independent of any inference traffic.
+    let heartbeat_tx = outbound_tx.clone();
+    let heartbeat_ollama_url = ollama_url.clone();
+    let heartbeat_task = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        interval.tick().await; // first tick fires immediately; skip it
+        loop {
+            interval.tick().await;
+
+            let current_load = APP_STATE.current_load.load(Ordering::Relaxed);
+            let gpu_memory_used = ollama::get_gpu_memory_used(&heartbeat_ollama_url).await;
+            let gpu_memory_total = ollama::get_gpu_memory_total().await;
+
+            let heartbeat_msg = ClientMessage {
+                msg_type: "HEARTBEAT".to_string(),
+                request_id: None,
+                result: None,
+                error: None,
+                models: None,
+                health: Some(HealthReport {
+                    current_load,
+                    capacity,
+                    gpu_memory_used,
+                    gpu_memory_total,
+                }),
+                delta: None,
+                kind: None,
+            };
+            match serde_json::to_string(&heartbeat_msg) {
+                Ok(text) => {
+                    if heartbeat_tx.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => log::error!("Failed to serialize heartbeat: {}", e),
+            }
+        }
+    });
+
+    let mut authenticated = false;
+    let mut exit_reason = SessionExit::ConnectionLost { authenticated: false };
+
     loop {
         tokio::select! {
             _ = disconnect_rx.recv() => {
                 log::info!("Disconnect signal received");
+                exit_reason = SessionExit::UserDisconnect;
                 break;
             }
             msg = read.next() => {
@@ -123,17 +521,26 @@ pub async fn connect_to_server(
                                 match server_msg {
                                     ServerMessage::AUTH_SUCCESS { operator_id, message } => {
                                         log::info!("Authenticated: {} - {}", operator_id, message);
-                                        
+                                        authenticated = true;
+
                                         {
-                                            let mut state = APP_STATE.write();
-                                            state.operator_id = Some(operator_id);
-                                            state.connected = true;
+                                            let mut inner = APP_STATE.inner.write();
+                                            inner.operator_id = Some(operator_id.clone());
+                                            inner.reconnect_attempts = 0;
                                         }
-                                        
+                                        APP_STATE.connected.store(true, Ordering::Relaxed);
+
+                                        let _ = app_handle
+                                            .notification()
+                                            .builder()
+                                            .title("PIN Client")
+                                            .body(format!("Connected to operator {}", operator_id))
+                                            .show();
+
                                         if let Ok(models) = ollama::get_models(&ollama_url).await {
                                             {
-                                                let mut state = APP_STATE.write();
-                                                state.models = models.clone();
+                                                let mut inner = APP_STATE.inner.write();
+                                                inner.models = models.clone();
                                             }
                                             let model_msg = ClientMessage {
                                                 msg_type: "MODEL_LIST".to_string(),
@@ -142,14 +549,16 @@ pub async fn connect_to_server(
                                                 result: None,
                                                 error: None,
                                                 health: None,
+                                                delta: None,
+                                                kind: None,
                                             };
-                                            let _ = write.send(Message::Text(serde_json::to_string(&model_msg)?)).await;
+                                            let _ = outbound_tx.send(Message::Text(serde_json::to_string(&model_msg)?)).await;
                                         }
                                     }
                                     ServerMessage::ERROR { message } => {
                                         log::error!("Server error: {}", message);
-                                        let mut state = APP_STATE.write();
-                                        state.connected = false;
+                                        APP_STATE.connected.store(false, Ordering::Relaxed);
+                                        exit_reason = SessionExit::ConnectionLost { authenticated };
                                         break;
                                     }
                                     ServerMessage::PING => {
@@ -160,56 +569,49 @@ pub async fn connect_to_server(
                                             error: None,
                                             models: None,
                                             health: None,
+                                            delta: None,
+                                            kind: None,
                                         };
-                                        let _ = write.send(Message::Text(serde_json::to_string(&pong)?)).await;
-                                        
-                                        let mut state = APP_STATE.write();
-                                        state.last_heartbeat = Some(chrono::Utc::now().to_rfc3339());
+                                        let _ = outbound_tx.send(Message::Text(serde_json::to_string(&pong)?)).await;
+
+                                        APP_STATE.inner.write().last_heartbeat = Some(chrono::Utc::now().to_rfc3339());
                                     }
                                     ServerMessage::HEARTBEAT_ACK | ServerMessage::MODEL_LIST_ACK => {
                                         log::debug!("Received ACK");
                                     }
+                                    ServerMessage::CHALLENGE { .. } => {
+                                        log::warn!("Unexpected CHALLENGE after auth, ignoring");
+                                    }
                                     ServerMessage::INFERENCE_REQUEST { request_id, payload } => {
                                         log::info!("Inference request: {} for model {}", request_id, payload.model);
-                                        
-                                        {
-                                            let mut state = APP_STATE.write();
-                                            state.current_load += 1;
-                                            state.total_requests += 1;
-                                        }
-                                        
-                                        let ollama_url_clone = ollama_url.clone();
-                                        let result = ollama::chat_completion(
-                                            &ollama_url_clone,
-                                            &payload.model,
-                                            payload.messages,
-                                            false,
-                                        ).await;
-                                        
-                                        let response = match result {
-                                            Ok(resp) => ClientMessage {
-                                                msg_type: "INFERENCE_RESPONSE".to_string(),
-                                                request_id: Some(request_id),
-                                                result: Some(serde_json::to_value(resp).unwrap()),
-                                                error: None,
-                                                models: None,
-                                                health: None,
-                                            },
-                                            Err(e) => ClientMessage {
-                                                msg_type: "INFERENCE_ERROR".to_string(),
-                                                request_id: Some(request_id),
-                                                result: None,
-                                                error: Some(e),
-                                                models: None,
-                                                health: None,
-                                            },
-                                        };
-                                        
-                                        let _ = write.send(Message::Text(serde_json::to_string(&response)?)).await;
-                                        
-                                        {
-                                            let mut state = APP_STATE.write();
-                                            state.current_load = state.current_load.saturating_sub(1);
+
+                                        match semaphore.clone().try_acquire_owned() {
+                                            Ok(permit) => {
+                                                let load_guard = LoadGuard::acquire();
+                                                APP_STATE.total_requests.fetch_add(1, Ordering::Relaxed);
+
+                                                let ollama_url_clone = ollama_url.clone();
+                                                let outbound_tx_clone = outbound_tx.clone();
+                                                inflight.spawn(async move {
+                                                    let _permit = permit;
+                                                    let _load_guard = load_guard;
+                                                    process_inference_request(request_id, payload, ollama_url_clone, outbound_tx_clone).await;
+                                                });
+                                            }
+                                            Err(_) => {
+                                                log::warn!("At capacity, rejecting inference request: {}", request_id);
+                                                let busy = ClientMessage {
+                                                    msg_type: "INFERENCE_ERROR".to_string(),
+                                                    request_id: Some(request_id),
+                                                    result: None,
+                                                    error: Some("No capacity available".to_string()),
+                                                    models: None,
+                                                    health: None,
+                                                    delta: None,
+                                                    kind: Some("at_capacity".to_string()),
+                                                };
+                                                let _ = outbound_tx.send(Message::Text(serde_json::to_string(&busy)?)).await;
+                                            }
                                         }
                                     }
                                 }
@@ -221,14 +623,17 @@ pub async fn connect_to_server(
                     }
                     Some(Ok(Message::Close(_))) => {
                         log::info!("Server closed connection");
+                        exit_reason = SessionExit::ConnectionLost { authenticated };
                         break;
                     }
                     Some(Err(e)) => {
                         log::error!("WebSocket error: {}", e);
+                        exit_reason = SessionExit::ConnectionLost { authenticated };
                         break;
                     }
                     None => {
                         log::info!("WebSocket stream ended");
+                        exit_reason = SessionExit::ConnectionLost { authenticated };
                         break;
                     }
                     _ => {}
@@ -236,16 +641,107 @@ pub async fn connect_to_server(
             }
         }
     }
-    
-    let mut state = APP_STATE.write();
-    state.connected = false;
+
+    heartbeat_task.abort();
+    inflight.abort_all();
+    drop(outbound_tx);
+    let _ = writer_task.await;
+
+    APP_STATE.connected.store(false, Ordering::Relaxed);
+
+    Ok(exit_reason)
+}
+
+/// Supervises [`run_session`], reconnecting with capped exponential backoff plus
+/// jitter whenever a session drops for any reason other than a user-requested
+/// `disconnect()`. The backoff resets once a session makes it through a full
+/// authenticated handshake, so a brief blip doesn't leave the client waiting
+/// out a long delay earned by earlier, unrelated failures.
+pub async fn connect_to_server(
+    server_url: &str,
+    client_id: &str,
+    credential: AuthCredential,
+    ollama_url: &str,
+    capacity: u32,
+    app_handle: tauri::AppHandle,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (disconnect_tx, mut disconnect_rx) = mpsc::channel::<()>(1);
+    *DISCONNECT_TX.write() = Some(disconnect_tx);
+
+    let mut delay = INITIAL_RECONNECT_DELAY;
+
+    loop {
+        let result = run_session(
+            server_url,
+            client_id,
+            &credential,
+            ollama_url,
+            capacity,
+            &mut disconnect_rx,
+            &app_handle,
+        )
+        .await;
+
+        let authenticated = match result {
+            Ok(SessionExit::UserDisconnect) => {
+                log::info!("Disconnected at user request");
+                break;
+            }
+            Ok(SessionExit::ConnectionLost { authenticated }) => authenticated,
+            Err(e) => {
+                log::error!("Session error: {}", e);
+                false
+            }
+        };
+
+        if authenticated {
+            delay = INITIAL_RECONNECT_DELAY;
+            APP_STATE.inner.write().reconnect_attempts = 0;
+        } else {
+            let attempts = {
+                let mut inner = APP_STATE.inner.write();
+                inner.reconnect_attempts += 1;
+                inner.reconnect_attempts
+            };
+            metrics::RECONNECT_COUNT.inc();
+            log::warn!("Reconnect attempt {} failed", attempts);
+        }
+
+        let jitter = Duration::from_millis(rand::thread_rng().gen_range(0..RECONNECT_JITTER_MS));
+        let sleep_for = delay + jitter;
+        log::info!("Reconnecting in {:?}", sleep_for);
+
+        let _ = app_handle
+            .notification()
+            .builder()
+            .title("PIN Client")
+            .body("Connection lost, reconnecting...")
+            .show();
+
+        tokio::select! {
+            _ = tokio::time::sleep(sleep_for) => {}
+            _ = disconnect_rx.recv() => {
+                log::info!("Disconnect requested while waiting to reconnect");
+                break;
+            }
+        }
+
+        metrics::RECONNECT_COUNT.inc();
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+
     *DISCONNECT_TX.write() = None;
-    
     Ok(())
 }
 
+/// Signals the supervisor (and any active session) to stop, whether it's
+/// mid-session or waiting out a reconnect backoff. Uses `try_send` rather than
+/// `blocking_send`: this is called from async Tauri commands running on a
+/// tokio worker thread, and `blocking_send` panics there. The channel only
+/// needs to carry one pending signal, so a full channel just means a
+/// disconnect is already in flight.
 pub fn disconnect() {
     if let Some(tx) = DISCONNECT_TX.read().clone() {
-        let _ = tx.blocking_send(());
+        let _ = tx.try_send(());
     }
 }